@@ -1,5 +1,4 @@
-use async_trait::async_trait;
-use redis::{self, cmd, AsyncCommands};
+use redis::{self, aio::ConnectionLike, cmd, AsyncCommands};
 use std::collections::{HashMap, HashSet};
 
 use crate::{
@@ -15,98 +14,214 @@ const NODES_KEY: &str = "nodes";
 const PROC_NODES_KEY: &str = "proc_nodes";
 const DEFAULT_NETWORK_KEY: &str = "default_network";
 
+/// Gets the default network. Shared by every [`NetdoxReader`] implementor so
+/// the query only needs to target the key owning the relevant slot once.
+async fn default_network<C: ConnectionLike + Send>(conn: &mut C) -> FCallResult<String> {
+    Ok(conn.get(DEFAULT_NETWORK_KEY).await?)
+}
+
+/// Qualifies a list of DNS names with the default network.
+async fn qualify_names<C: ConnectionLike + Send>(
+    conn: &mut C,
+    names: Vec<String>,
+) -> FCallResult<Vec<String>> {
+    Ok(cmd("FCALL")
+        .arg(QUALIFY_DNS_NAME_FN)
+        .arg(names.len() as u32)
+        .arg(&names)
+        .query_async(conn)
+        .await?)
+}
+
+/// Get all DNS names that have been registered.
+async fn dns_names<C: ConnectionLike + Send>(conn: &mut C) -> FCallResult<HashSet<String>> {
+    Ok(conn.smembers(DNS_KEY).await?)
+}
+
+/// Fetches the name and member sets of every node in `link_ids` using a
+/// single pipelined round trip, instead of five round trips per node. Against
+/// a cluster connection this is only safe when every key it reads hashes to
+/// the same slot, which the key layout does not guarantee even for a single
+/// node - see [`fetch_node_routed`].
+async fn fetch_nodes<C>(conn: &mut C, link_ids: &[String]) -> FCallResult<Vec<Node>>
+where
+    C: ConnectionLike + Send,
+{
+    if link_ids.is_empty() {
+        return Ok(Vec::new());
+    }
+
+    let mut pipe = redis::pipe();
+    for link_id in link_ids {
+        pipe.get(format!("{PROC_NODES_KEY};{link_id}"))
+            .smembers(format!("{PROC_NODES_KEY};{link_id};alt_names"))
+            .smembers(format!("{PROC_NODES_KEY};{link_id};dns_names"))
+            .smembers(format!("{PROC_NODES_KEY};{link_id};raw_ids"))
+            .smembers(format!("{PROC_NODES_KEY};{link_id};plugins"));
+    }
+
+    let replies: Vec<redis::Value> = pipe.query_async(conn).await?;
+
+    let mut nodes = Vec::with_capacity(link_ids.len());
+    for (link_id, fields) in link_ids.iter().zip(replies.chunks(5)) {
+        nodes.push(Node {
+            name: redis::from_redis_value(&fields[0])?,
+            link_id: link_id.clone(),
+            alt_names: redis::from_redis_value(&fields[1])?,
+            dns_names: redis::from_redis_value(&fields[2])?,
+            raw_ids: redis::from_redis_value(&fields[3])?,
+            plugins: redis::from_redis_value(&fields[4])?,
+        });
+    }
+
+    Ok(nodes)
+}
+
+/// Fetches a single node's fields with one awaited command per field,
+/// rather than a pipeline. The `proc_nodes;{link_id}` key and its
+/// `;alt_names`/`;dns_names`/`;raw_ids`/`;plugins` siblings are not
+/// guaranteed to share a hash slot (there's no hash-tag in the key
+/// layout), so on a cluster connection a single pipeline covering all
+/// five can return `CROSSSLOT` or silently miss replies depending on
+/// how the keys land. Used for [`redis::cluster_async::ClusterConnection`];
+/// [`fetch_nodes`] remains the fast path for a single-shard
+/// [`redis::aio::MultiplexedConnection`].
+async fn fetch_node_routed<C>(conn: &mut C, link_id: &str) -> FCallResult<Node>
+where
+    C: ConnectionLike + Send,
+{
+    Ok(Node {
+        name: conn.get(format!("{PROC_NODES_KEY};{link_id}")).await?,
+        link_id: link_id.to_string(),
+        alt_names: conn
+            .smembers(format!("{PROC_NODES_KEY};{link_id};alt_names"))
+            .await?,
+        dns_names: conn
+            .smembers(format!("{PROC_NODES_KEY};{link_id};dns_names"))
+            .await?,
+        raw_ids: conn
+            .smembers(format!("{PROC_NODES_KEY};{link_id};raw_ids"))
+            .await?,
+        plugins: conn
+            .smembers(format!("{PROC_NODES_KEY};{link_id};plugins"))
+            .await?,
+    })
+}
+
+/// Get metadata for a DNS name.
+async fn dns_metadata<C: ConnectionLike + Send>(
+    conn: &mut C,
+    name: &str,
+) -> FCallResult<HashMap<String, String>> {
+    let qualified_name = match qualify_names(conn, vec![name.to_string()])
+        .await?
+        .into_iter()
+        .next()
+    {
+        Some(qn) => qn,
+        None => {
+            return Err(FCallError::Logic(
+                "Tried to qualify one DNS name but got zero back.",
+            ))
+        }
+    };
+
+    Ok(conn
+        .hgetall(format!("{META_KEY};{DNS_KEY};{qualified_name}"))
+        .await?)
+}
+
+/// Get metadata for a node, with raw metadata overlaid by processed metadata.
+async fn node_metadata<C: ConnectionLike + Send>(
+    conn: &mut C,
+    node: &Node,
+) -> FCallResult<HashMap<String, String>> {
+    let mut meta = HashMap::new();
+    for raw_id in &node.raw_ids {
+        let raw_meta: HashMap<String, String> = conn
+            .hgetall(format!("{META_KEY};{NODES_KEY};{raw_id}"))
+            .await?;
+        meta.extend(raw_meta);
+    }
+    let proc_meta: HashMap<String, String> = conn
+        .hgetall(format!("{META_KEY};{PROC_NODES_KEY};{}", node.link_id))
+        .await?;
+    meta.extend(proc_meta);
+    Ok(meta)
+}
+
 // Implementing the trait for redis::aio::MultiplexedConnection
-#[async_trait]
 impl NetdoxReader for redis::aio::MultiplexedConnection {
-    /// Gets the default network.
     async fn get_default_network(&mut self) -> FCallResult<String> {
-        Ok(self.get(DEFAULT_NETWORK_KEY).await?)
+        default_network(self).await
+    }
+
+    async fn qualify_dns_names(&mut self, names: Vec<String>) -> FCallResult<Vec<String>> {
+        qualify_names(self, names).await
+    }
+
+    async fn get_dns_names(&mut self) -> FCallResult<HashSet<String>> {
+        dns_names(self).await
+    }
+
+    async fn get_nodes(&mut self) -> FCallResult<Vec<Node>> {
+        let link_ids: Vec<String> = self.smembers(PROC_NODES_KEY).await?;
+        fetch_nodes(self, &link_ids).await
+    }
+
+    async fn get_node(&mut self, link_id: &str) -> FCallResult<Node> {
+        Ok(fetch_nodes(self, std::slice::from_ref(&link_id.to_string()))
+            .await?
+            .remove(0))
+    }
+
+    async fn get_dns_metadata(&mut self, name: &str) -> FCallResult<HashMap<String, String>> {
+        dns_metadata(self, name).await
+    }
+
+    async fn get_node_metadata(&mut self, node: &Node) -> FCallResult<HashMap<String, String>> {
+        node_metadata(self, node).await
+    }
+}
+
+// Implementing the trait for redis::cluster_async::ClusterConnection, so
+// plugins can run against sharded redis/valkey deployments. Every FCALL
+// above already passes its real key(s) as the key-count-prefixed arguments,
+// so the cluster client can route it to the slot owning them. get_nodes and
+// get_node use fetch_node_routed rather than the pipelined fetch_nodes: a
+// node's fields live under distinct keys with no shared hash tag, so they
+// aren't guaranteed to land on the same slot and can't safely share a pipeline.
+impl NetdoxReader for redis::cluster_async::ClusterConnection {
+    async fn get_default_network(&mut self) -> FCallResult<String> {
+        default_network(self).await
     }
 
-    /// Qualifies a list of DNS names with the default network.
     async fn qualify_dns_names(&mut self, names: Vec<String>) -> FCallResult<Vec<String>> {
-        Ok(cmd(QUALIFY_DNS_NAME_FN)
-            .arg(names.len() as u32)
-            .arg(&names)
-            .query_async(self)
-            .await?)
+        qualify_names(self, names).await
     }
 
-    /// Get all DNS names that have been registered.
     async fn get_dns_names(&mut self) -> FCallResult<HashSet<String>> {
-        Ok(self.smembers(DNS_KEY).await?)
+        dns_names(self).await
     }
 
-    /// Get all nodes in the database.
     async fn get_nodes(&mut self) -> FCallResult<Vec<Node>> {
-        let mut nodes = Vec::new();
         let link_ids: Vec<String> = self.smembers(PROC_NODES_KEY).await?;
+        let mut nodes = Vec::with_capacity(link_ids.len());
         for link_id in link_ids {
-            nodes.push(self.get_node(&link_id).await?);
+            nodes.push(fetch_node_routed(self, &link_id).await?);
         }
         Ok(nodes)
     }
 
-    /// Get a node by its link ID.
     async fn get_node(&mut self, link_id: &str) -> FCallResult<Node> {
-        let name: String = self.get(format!("{PROC_NODES_KEY};{link_id}")).await?;
-        let alt_names: HashSet<String> = self
-            .smembers(format!("{PROC_NODES_KEY};{link_id};alt_names"))
-            .await?;
-        let dns_names: HashSet<String> = self
-            .smembers(format!("{PROC_NODES_KEY};{link_id};dns_names"))
-            .await?;
-        let raw_ids: HashSet<String> = self
-            .smembers(format!("{PROC_NODES_KEY};{link_id};raw_ids"))
-            .await?;
-        let plugins: HashSet<String> = self
-            .smembers(format!("{PROC_NODES_KEY};{link_id};plugins"))
-            .await?;
-
-        Ok(Node {
-            name,
-            link_id: link_id.to_string(),
-            alt_names,
-            dns_names,
-            raw_ids,
-            plugins,
-        })
+        fetch_node_routed(self, link_id).await
     }
 
-    /// Get metadata for a DNS name.
     async fn get_dns_metadata(&mut self, name: &str) -> FCallResult<HashMap<String, String>> {
-        let qualified_name = match self
-            .qualify_dns_names(vec![name.to_string()])
-            .await?
-            .into_iter()
-            .next()
-        {
-            Some(qn) => qn,
-            None => {
-                return Err(FCallError::Logic(
-                    "Tried to qualify one DNS name but got zero back.",
-                ))
-            }
-        };
-
-        Ok(self
-            .hgetall(format!("{META_KEY};{DNS_KEY};{qualified_name}"))
-            .await?)
-    }
-
-    /// Get metadata for a node.
+        dns_metadata(self, name).await
+    }
+
     async fn get_node_metadata(&mut self, node: &Node) -> FCallResult<HashMap<String, String>> {
-        let mut meta = HashMap::new();
-        for raw_id in &node.raw_ids {
-            let raw_meta: HashMap<String, String> = self
-                .hgetall(format!("{META_KEY};{NODES_KEY};{raw_id}"))
-                .await?;
-            meta.extend(raw_meta);
-        }
-        let proc_meta: HashMap<String, String> = self
-            .hgetall(format!("{META_KEY};{PROC_NODES_KEY};{}", node.link_id))
-            .await?;
-        meta.extend(proc_meta);
-        Ok(meta)
+        node_metadata(self, node).await
     }
 }
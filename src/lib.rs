@@ -0,0 +1,8 @@
+pub mod error;
+pub mod get;
+pub mod model;
+pub mod put;
+pub mod subscribe;
+
+#[cfg(feature = "mocks")]
+pub mod mock;
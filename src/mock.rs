@@ -0,0 +1,454 @@
+//! An in-memory backend for testing plugins without a live redis instance.
+
+use std::collections::{HashMap, HashSet};
+
+use crate::{
+    error::{FCallError, FCallResult},
+    model::{NetdoxReader, NetdoxWriter, Node, PluginData},
+};
+
+/// An owned copy of [`PluginData`], kept so a [`MockStore`] can be inspected
+/// after a plugin data write without fighting the borrowed variant's lifetime.
+/// The string variant keeps its title and content but not its content type.
+#[derive(Debug, Clone, PartialEq)]
+pub enum StoredPluginData {
+    Hash {
+        title: String,
+        items: HashMap<String, String>,
+    },
+    List {
+        title: String,
+        items: Vec<(String, String, String)>,
+    },
+    String {
+        title: String,
+        content: String,
+    },
+    Table {
+        title: String,
+        num_columns: usize,
+        rows: Vec<Vec<String>>,
+    },
+}
+
+impl<'a> From<&PluginData<'a>> for StoredPluginData {
+    fn from(data: &PluginData<'a>) -> Self {
+        match data {
+            PluginData::Hash { title, items } => StoredPluginData::Hash {
+                title: title.to_string(),
+                items: items
+                    .iter()
+                    .map(|(k, v)| (k.to_string(), v.to_string()))
+                    .collect(),
+            },
+            PluginData::List { title, items } => StoredPluginData::List {
+                title: title.to_string(),
+                items: items
+                    .iter()
+                    .map(|(a, b, c)| (a.to_string(), b.to_string(), c.to_string()))
+                    .collect(),
+            },
+            PluginData::String { title, content, .. } => StoredPluginData::String {
+                title: title.to_string(),
+                content: content.to_string(),
+            },
+            PluginData::Table {
+                title,
+                num_columns,
+                rows,
+            } => StoredPluginData::Table {
+                title: title.to_string(),
+                num_columns: *num_columns,
+                rows: rows
+                    .iter()
+                    .map(|row| row.iter().map(|col| col.to_string()).collect())
+                    .collect(),
+            },
+        }
+    }
+}
+
+#[derive(Debug, Clone, Default)]
+struct MockNode {
+    name: String,
+    alt_names: HashSet<String>,
+    dns_names: HashSet<String>,
+    raw_ids: HashSet<String>,
+    plugins: HashSet<String>,
+}
+
+/// Computes the synthetic raw node ID this mock uses to tie a set of DNS
+/// names back to the raw node they were registered under, since the mock
+/// has no Lua reconciliation step to derive one for us.
+fn raw_id_for(dns_names: &[&str]) -> String {
+    let mut names: Vec<&str> = dns_names.to_vec();
+    names.sort_unstable();
+    names.join(",")
+}
+
+/// An in-memory stand-in for a redis connection, implementing [`NetdoxReader`]
+/// and [`NetdoxWriter`] over plain `HashMap`/`HashSet` structures.
+///
+/// Mirrors the `meta;…`, `proc_nodes;…`, and `dns` key layouts used by the
+/// redis-backed implementations closely enough that plugin code can be
+/// exercised in tests without a server. Only available behind the `mocks`
+/// feature.
+#[derive(Debug, Default)]
+pub struct MockStore {
+    default_network: String,
+    dns_names: HashSet<String>,
+    dns_metadata: HashMap<String, HashMap<String, String>>,
+    dns_plugin_data: HashMap<(String, String), StoredPluginData>,
+    proc_nodes: HashMap<String, MockNode>,
+    node_metadata: HashMap<String, HashMap<String, String>>,
+    proc_node_metadata: HashMap<String, HashMap<String, String>>,
+    node_plugin_data: HashMap<(String, String), StoredPluginData>,
+    proc_node_plugin_data: HashMap<(String, String), StoredPluginData>,
+    reports: HashMap<String, (String, String, usize)>,
+    report_data: HashMap<(String, usize), StoredPluginData>,
+}
+
+impl MockStore {
+    /// Creates an empty store using `default_network` as the network
+    /// namespace returned by [`NetdoxReader::get_default_network`].
+    pub fn new(default_network: impl Into<String>) -> Self {
+        MockStore {
+            default_network: default_network.into(),
+            ..Default::default()
+        }
+    }
+
+    /// Returns the plugin data attached to a DNS name under `pdata_id`, if any.
+    pub fn dns_plugin_data(&self, name: &str, pdata_id: &str) -> Option<&StoredPluginData> {
+        self.dns_plugin_data
+            .get(&(name.to_string(), pdata_id.to_string()))
+    }
+
+    /// Returns the plugin data attached to a raw node under `pdata_id`, if any.
+    /// `dns_names` must be the same set passed to the `put_node`/
+    /// `put_node_plugin_data` call that created it - see [`raw_id_for`].
+    pub fn node_plugin_data(&self, dns_names: &[&str], pdata_id: &str) -> Option<&StoredPluginData> {
+        self.node_plugin_data
+            .get(&(raw_id_for(dns_names), pdata_id.to_string()))
+    }
+
+    /// Returns the plugin data attached to a processed node under `pdata_id`, if any.
+    pub fn proc_node_plugin_data(
+        &self,
+        link_id: &str,
+        pdata_id: &str,
+    ) -> Option<&StoredPluginData> {
+        self.proc_node_plugin_data
+            .get(&(link_id.to_string(), pdata_id.to_string()))
+    }
+
+    /// Returns the plugin, title and length of a report, if it was created.
+    pub fn report(&self, report_id: &str) -> Option<&(String, String, usize)> {
+        self.reports.get(report_id)
+    }
+
+    /// Returns the data attached to a report at `index`, if any.
+    pub fn report_data(&self, report_id: &str, index: usize) -> Option<&StoredPluginData> {
+        self.report_data.get(&(report_id.to_string(), index))
+    }
+}
+
+impl NetdoxReader for MockStore {
+    async fn get_default_network(&mut self) -> FCallResult<String> {
+        Ok(self.default_network.clone())
+    }
+
+    async fn qualify_dns_names(&mut self, names: Vec<String>) -> FCallResult<Vec<String>> {
+        Ok(names
+            .into_iter()
+            .map(|name| format!("[{}]{name}", self.default_network))
+            .collect())
+    }
+
+    async fn get_dns_names(&mut self) -> FCallResult<HashSet<String>> {
+        Ok(self.dns_names.clone())
+    }
+
+    async fn get_nodes(&mut self) -> FCallResult<Vec<Node>> {
+        let link_ids: Vec<String> = self.proc_nodes.keys().cloned().collect();
+        let mut nodes = Vec::with_capacity(link_ids.len());
+        for link_id in link_ids {
+            nodes.push(self.get_node(&link_id).await?);
+        }
+        Ok(nodes)
+    }
+
+    async fn get_node(&mut self, link_id: &str) -> FCallResult<Node> {
+        let node = self
+            .proc_nodes
+            .get(link_id)
+            .ok_or(FCallError::Logic("no node with that link ID in the mock store"))?;
+
+        Ok(Node {
+            name: node.name.clone(),
+            link_id: link_id.to_string(),
+            alt_names: node.alt_names.clone(),
+            dns_names: node.dns_names.clone(),
+            raw_ids: node.raw_ids.clone(),
+            plugins: node.plugins.clone(),
+        })
+    }
+
+    async fn get_dns_metadata(&mut self, name: &str) -> FCallResult<HashMap<String, String>> {
+        let qualified_name = match self
+            .qualify_dns_names(vec![name.to_string()])
+            .await?
+            .into_iter()
+            .next()
+        {
+            Some(qn) => qn,
+            None => {
+                return Err(FCallError::Logic(
+                    "Tried to qualify one DNS name but got zero back.",
+                ))
+            }
+        };
+
+        Ok(self
+            .dns_metadata
+            .get(&qualified_name)
+            .cloned()
+            .unwrap_or_default())
+    }
+
+    async fn get_node_metadata(&mut self, node: &Node) -> FCallResult<HashMap<String, String>> {
+        let mut meta = HashMap::new();
+        for raw_id in &node.raw_ids {
+            if let Some(raw_meta) = self.node_metadata.get(raw_id) {
+                meta.extend(raw_meta.clone());
+            }
+        }
+        if let Some(proc_meta) = self.proc_node_metadata.get(&node.link_id) {
+            meta.extend(proc_meta.clone());
+        }
+        Ok(meta)
+    }
+}
+
+impl NetdoxWriter for MockStore {
+    async fn put_dns(
+        &mut self,
+        _plugin: &str,
+        name: &str,
+        rtype: Option<&str>,
+        value: Option<&str>,
+    ) -> FCallResult<()> {
+        match (rtype, value) {
+            (Some(_), Some(_)) | (None, None) => {}
+            _ => {
+                return Err(FCallError::WrongArgs {
+                    function: "put_dns",
+                    problem: "record type and value must both be provided or neiher provided.",
+                })
+            }
+        }
+
+        let qualified = self
+            .qualify_dns_names(vec![name.to_string()])
+            .await?
+            .remove(0);
+        self.dns_names.insert(qualified);
+        Ok(())
+    }
+
+    async fn put_dns_plugin_data<'a>(
+        &mut self,
+        _plugin: &str,
+        name: &str,
+        pdata_id: &str,
+        data: PluginData<'a>,
+    ) -> FCallResult<()> {
+        self.dns_plugin_data.insert(
+            (name.to_string(), pdata_id.to_string()),
+            StoredPluginData::from(&data),
+        );
+        Ok(())
+    }
+
+    async fn put_dns_metadata(
+        &mut self,
+        _plugin: &str,
+        name: &str,
+        metadata: &HashMap<&str, &str>,
+    ) -> FCallResult<()> {
+        let qualified = self
+            .qualify_dns_names(vec![name.to_string()])
+            .await?
+            .remove(0);
+
+        let entry = self.dns_metadata.entry(qualified).or_default();
+        for (key, val) in metadata {
+            entry.insert(key.to_string(), val.to_string());
+        }
+        Ok(())
+    }
+
+    async fn put_node(
+        &mut self,
+        plugin: &str,
+        name: &str,
+        dns_names: Vec<&str>,
+        _exclusive: bool,
+        link_id: Option<&str>,
+    ) -> FCallResult<()> {
+        let link_id = link_id.unwrap_or(name).to_string();
+        let raw_id = raw_id_for(&dns_names);
+
+        let node = self.proc_nodes.entry(link_id).or_default();
+        node.name = name.to_string();
+        node.dns_names.extend(dns_names.into_iter().map(String::from));
+        node.raw_ids.insert(raw_id);
+        node.plugins.insert(plugin.to_string());
+        Ok(())
+    }
+
+    async fn put_node_plugin_data<'a>(
+        &mut self,
+        _plugin: &str,
+        dns_names: Vec<&str>,
+        pdata_id: &str,
+        data: PluginData<'a>,
+    ) -> FCallResult<()> {
+        let raw_id = raw_id_for(&dns_names);
+        self.node_plugin_data
+            .insert((raw_id, pdata_id.to_string()), StoredPluginData::from(&data));
+        Ok(())
+    }
+
+    async fn put_proc_node_plugin_data<'a>(
+        &mut self,
+        _plugin: &str,
+        link_id: &str,
+        pdata_id: &str,
+        data: PluginData<'a>,
+    ) -> FCallResult<()> {
+        self.proc_node_plugin_data.insert(
+            (link_id.to_string(), pdata_id.to_string()),
+            StoredPluginData::from(&data),
+        );
+        Ok(())
+    }
+
+    async fn put_node_metadata(
+        &mut self,
+        _plugin: &str,
+        dns_names: Vec<&str>,
+        metadata: &HashMap<&str, &str>,
+    ) -> FCallResult<()> {
+        let raw_id = raw_id_for(&dns_names);
+        let entry = self.node_metadata.entry(raw_id).or_default();
+        for (key, val) in metadata {
+            entry.insert(key.to_string(), val.to_string());
+        }
+        Ok(())
+    }
+
+    async fn put_proc_node_metadata(
+        &mut self,
+        _plugin: &str,
+        link_id: &str,
+        metadata: &HashMap<&str, &str>,
+    ) -> FCallResult<()> {
+        let entry = self
+            .proc_node_metadata
+            .entry(link_id.to_string())
+            .or_default();
+        for (key, val) in metadata {
+            entry.insert(key.to_string(), val.to_string());
+        }
+        Ok(())
+    }
+
+    async fn put_report(
+        &mut self,
+        plugin: &str,
+        report_id: &str,
+        title: &str,
+        length: usize,
+    ) -> FCallResult<()> {
+        self.reports.insert(
+            report_id.to_string(),
+            (plugin.to_string(), title.to_string(), length),
+        );
+        Ok(())
+    }
+
+    async fn put_report_data<'a>(
+        &mut self,
+        _plugin: &str,
+        report_id: &str,
+        index: usize,
+        data: PluginData<'a>,
+    ) -> FCallResult<()> {
+        self.report_data
+            .insert((report_id.to_string(), index), StoredPluginData::from(&data));
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn dns_round_trip() {
+        let mut store = MockStore::new("default");
+        store.put_dns("test-plugin", "example.com", None, None).await.unwrap();
+
+        let names = store.get_dns_names().await.unwrap();
+        assert!(names.contains("[default]example.com"));
+    }
+
+    #[tokio::test]
+    async fn node_metadata_merges_proc_over_raw() {
+        let mut store = MockStore::new("default");
+        store
+            .put_node("test-plugin", "node-a", vec!["a.com"], false, Some("link-1"))
+            .await
+            .unwrap();
+
+        store
+            .put_node_metadata(
+                "test-plugin",
+                vec!["a.com"],
+                &HashMap::from([("colour", "red"), ("owner", "raw")]),
+            )
+            .await
+            .unwrap();
+        store
+            .put_proc_node_metadata(
+                "test-plugin",
+                "link-1",
+                &HashMap::from([("owner", "proc")]),
+            )
+            .await
+            .unwrap();
+
+        let node = store.get_node("link-1").await.unwrap();
+        let meta = store.get_node_metadata(&node).await.unwrap();
+
+        assert_eq!(meta.get("colour").map(String::as_str), Some("red"));
+        assert_eq!(meta.get("owner").map(String::as_str), Some("proc"));
+    }
+
+    #[tokio::test]
+    async fn dns_metadata_round_trip() {
+        let mut store = MockStore::new("default");
+        store
+            .put_dns_metadata(
+                "test-plugin",
+                "example.com",
+                &HashMap::from([("owner", "infra")]),
+            )
+            .await
+            .unwrap();
+
+        let meta = store.get_dns_metadata("example.com").await.unwrap();
+        assert_eq!(meta.get("owner").map(String::as_str), Some("infra"));
+    }
+}
@@ -1,5 +1,7 @@
 use std::collections::HashMap;
 
+use redis::aio::ConnectionLike;
+
 use crate::{
     error::{FCallError, FCallResult},
     model::{NetdoxWriter, PluginData},
@@ -18,9 +20,314 @@ const CREATE_DNS_METADATA_FN: &str = "netdox_create_dns_metadata";
 const CREATE_NODE_METADATA_FN: &str = "netdox_create_node_metadata";
 const CREATE_PROC_NODE_METADATA_FN: &str = "netdox_create_proc_node_metadata";
 
+// DNS
+
+async fn put_dns<C: ConnectionLike + Send>(
+    conn: &mut C,
+    plugin: &str,
+    name: &str,
+    rtype: Option<&str>,
+    value: Option<&str>,
+) -> FCallResult<()> {
+    let mut cmd = redis::cmd("FCALL");
+    cmd.arg(CREATE_DNS_FN).arg(1).arg(name).arg(plugin);
+
+    match (rtype, value) {
+        (Some(rtype), Some(value)) => Ok(cmd.arg(rtype).arg(value).exec_async(conn).await?),
+        (None, None) => Ok(cmd.exec_async(conn).await?),
+        _ => Err(FCallError::WrongArgs {
+            function: CREATE_DNS_FN,
+            problem: "record type and value must both be provided or neiher provided.",
+        }),
+    }
+}
+
+async fn put_dns_plugin_data<'a, C: ConnectionLike + Send>(
+    conn: &mut C,
+    plugin: &str,
+    name: &str,
+    pdata_id: &str,
+    data: PluginData<'a>,
+) -> FCallResult<()> {
+    let mut cmd = redis::cmd("FCALL");
+    cmd.arg(CREATE_DNS_PDATA_FN)
+        .arg(1)
+        .arg(name)
+        .arg(plugin)
+        .arg(pdata_id);
+
+    data.add_as_args(&mut cmd);
+
+    Ok(cmd.exec_async(conn).await?)
+}
+
+async fn put_dns_metadata<C: ConnectionLike + Send>(
+    conn: &mut C,
+    plugin: &str,
+    name: &str,
+    metadata: &HashMap<&str, &str>,
+) -> FCallResult<()> {
+    let mut cmd = redis::cmd("FCALL");
+    cmd.arg(CREATE_DNS_METADATA_FN).arg(1).arg(name).arg(plugin);
+
+    for (key, val) in metadata {
+        cmd.arg(key).arg(val);
+    }
+
+    Ok(cmd.exec_async(conn).await?)
+}
+
+// Nodes
+
+async fn put_node<C: ConnectionLike + Send>(
+    conn: &mut C,
+    plugin: &str,
+    name: &str,
+    dns_names: Vec<&str>,
+    exclusive: bool,
+    link_id: Option<&str>,
+) -> FCallResult<()> {
+    let mut cmd = redis::cmd("FCALL");
+    cmd.arg(CREATE_NODE_FN).arg(dns_names.len());
+
+    for name in dns_names {
+        cmd.arg(name);
+    }
+
+    cmd.arg(plugin).arg(name).arg(exclusive);
+
+    if let Some(link_id) = link_id {
+        cmd.arg(link_id);
+    }
+
+    Ok(cmd.exec_async(conn).await?)
+}
+
+async fn put_node_plugin_data<'a, C: ConnectionLike + Send>(
+    conn: &mut C,
+    plugin: &str,
+    dns_names: Vec<&str>,
+    pdata_id: &str,
+    data: PluginData<'a>,
+) -> FCallResult<()> {
+    let mut cmd = redis::cmd("FCALL");
+    cmd.arg(CREATE_NODE_PDATA_FN).arg(dns_names.len());
+
+    for name in dns_names {
+        cmd.arg(name);
+    }
+
+    cmd.arg(plugin).arg(pdata_id);
+
+    data.add_as_args(&mut cmd);
+
+    Ok(cmd.exec_async(conn).await?)
+}
+
+async fn put_proc_node_plugin_data<'a, C: ConnectionLike + Send>(
+    conn: &mut C,
+    plugin: &str,
+    link_id: &str,
+    pdata_id: &str,
+    data: PluginData<'a>,
+) -> FCallResult<()> {
+    let mut cmd = redis::cmd("FCALL");
+    cmd.arg(CREATE_PROC_NODE_PDATA_FN)
+        .arg(1)
+        .arg(link_id)
+        .arg(plugin)
+        .arg(pdata_id);
+
+    data.add_as_args(&mut cmd);
+
+    Ok(cmd.exec_async(conn).await?)
+}
+
+async fn put_node_metadata<C: ConnectionLike + Send>(
+    conn: &mut C,
+    plugin: &str,
+    dns_names: Vec<&str>,
+    metadata: &HashMap<&str, &str>,
+) -> FCallResult<()> {
+    let mut cmd = redis::cmd("FCALL");
+    cmd.arg(CREATE_NODE_METADATA_FN).arg(dns_names.len());
+    for name in dns_names {
+        cmd.arg(name);
+    }
+    cmd.arg(plugin);
+
+    for (key, val) in metadata {
+        cmd.arg(key).arg(val);
+    }
+
+    Ok(cmd.exec_async(conn).await?)
+}
+
+async fn put_proc_node_metadata<C: ConnectionLike + Send>(
+    conn: &mut C,
+    plugin: &str,
+    link_id: &str,
+    metadata: &HashMap<&str, &str>,
+) -> FCallResult<()> {
+    let mut cmd = redis::cmd("FCALL");
+    cmd.arg(CREATE_PROC_NODE_METADATA_FN)
+        .arg(1)
+        .arg(link_id)
+        .arg(plugin);
+
+    for (key, val) in metadata {
+        cmd.arg(key).arg(val);
+    }
+
+    Ok(cmd.exec_async(conn).await?)
+}
+
+// Reports
+
+async fn put_report<C: ConnectionLike + Send>(
+    conn: &mut C,
+    plugin: &str,
+    report_id: &str,
+    title: &str,
+    length: usize,
+) -> FCallResult<()> {
+    let mut cmd = redis::cmd("FCALL");
+
+    cmd.arg(CREATE_REPORT_FN)
+        .arg(1)
+        .arg(report_id)
+        .arg(plugin)
+        .arg(title)
+        .arg(length);
+
+    Ok(cmd.exec_async(conn).await?)
+}
+
+async fn put_report_data<'a, C: ConnectionLike + Send>(
+    conn: &mut C,
+    plugin: &str,
+    report_id: &str,
+    index: usize,
+    data: PluginData<'a>,
+) -> FCallResult<()> {
+    let mut cmd = redis::cmd("FCALL");
+    cmd.arg(CREATE_REPORT_DATA_FN)
+        .arg(1)
+        .arg(report_id)
+        .arg(plugin)
+        .arg(index);
+
+    data.add_as_args(&mut cmd);
+
+    Ok(cmd.exec_async(conn).await?)
+}
+
 impl NetdoxWriter for redis::aio::MultiplexedConnection {
-    // DNS
+    async fn put_dns(
+        &mut self,
+        plugin: &str,
+        name: &str,
+        rtype: Option<&str>,
+        value: Option<&str>,
+    ) -> FCallResult<()> {
+        put_dns(self, plugin, name, rtype, value).await
+    }
+
+    async fn put_dns_plugin_data<'a>(
+        &mut self,
+        plugin: &str,
+        name: &str,
+        pdata_id: &str,
+        data: PluginData<'a>,
+    ) -> FCallResult<()> {
+        put_dns_plugin_data(self, plugin, name, pdata_id, data).await
+    }
+
+    async fn put_dns_metadata(
+        &mut self,
+        plugin: &str,
+        name: &str,
+        metadata: &HashMap<&str, &str>,
+    ) -> FCallResult<()> {
+        put_dns_metadata(self, plugin, name, metadata).await
+    }
+
+    async fn put_node(
+        &mut self,
+        plugin: &str,
+        name: &str,
+        dns_names: Vec<&str>,
+        exclusive: bool,
+        link_id: Option<&str>,
+    ) -> FCallResult<()> {
+        put_node(self, plugin, name, dns_names, exclusive, link_id).await
+    }
+
+    async fn put_node_plugin_data<'a>(
+        &mut self,
+        plugin: &str,
+        dns_names: Vec<&str>,
+        pdata_id: &str,
+        data: PluginData<'a>,
+    ) -> FCallResult<()> {
+        put_node_plugin_data(self, plugin, dns_names, pdata_id, data).await
+    }
+
+    async fn put_proc_node_plugin_data<'a>(
+        &mut self,
+        plugin: &str,
+        link_id: &str,
+        pdata_id: &str,
+        data: PluginData<'a>,
+    ) -> FCallResult<()> {
+        put_proc_node_plugin_data(self, plugin, link_id, pdata_id, data).await
+    }
 
+    async fn put_node_metadata(
+        &mut self,
+        plugin: &str,
+        dns_names: Vec<&str>,
+        metadata: &HashMap<&str, &str>,
+    ) -> FCallResult<()> {
+        put_node_metadata(self, plugin, dns_names, metadata).await
+    }
+
+    async fn put_proc_node_metadata(
+        &mut self,
+        plugin: &str,
+        link_id: &str,
+        metadata: &HashMap<&str, &str>,
+    ) -> FCallResult<()> {
+        put_proc_node_metadata(self, plugin, link_id, metadata).await
+    }
+
+    async fn put_report(
+        &mut self,
+        plugin: &str,
+        report_id: &str,
+        title: &str,
+        length: usize,
+    ) -> FCallResult<()> {
+        put_report(self, plugin, report_id, title, length).await
+    }
+
+    async fn put_report_data<'a>(
+        &mut self,
+        plugin: &str,
+        report_id: &str,
+        index: usize,
+        data: PluginData<'a>,
+    ) -> FCallResult<()> {
+        put_report_data(self, plugin, report_id, index, data).await
+    }
+}
+
+// Implementing the trait for redis::cluster_async::ClusterConnection, so
+// plugins can run against sharded redis/valkey deployments. Every FCALL
+// above already passes its real key(s) as the key-count-prefixed arguments
+// (e.g. `arg(1).arg(name)` or `arg(dns_names.len())` followed by each name),
+// which is what lets the cluster client route the call to the slot owning it.
+impl NetdoxWriter for redis::cluster_async::ClusterConnection {
     async fn put_dns(
         &mut self,
         plugin: &str,
@@ -28,65 +335,208 @@ impl NetdoxWriter for redis::aio::MultiplexedConnection {
         rtype: Option<&str>,
         value: Option<&str>,
     ) -> FCallResult<()> {
-        let mut cmd = redis::cmd("FCALL");
-        cmd.arg(CREATE_DNS_FN).arg(1).arg(name).arg(plugin);
+        put_dns(self, plugin, name, rtype, value).await
+    }
+
+    async fn put_dns_plugin_data<'a>(
+        &mut self,
+        plugin: &str,
+        name: &str,
+        pdata_id: &str,
+        data: PluginData<'a>,
+    ) -> FCallResult<()> {
+        put_dns_plugin_data(self, plugin, name, pdata_id, data).await
+    }
+
+    async fn put_dns_metadata(
+        &mut self,
+        plugin: &str,
+        name: &str,
+        metadata: &HashMap<&str, &str>,
+    ) -> FCallResult<()> {
+        put_dns_metadata(self, plugin, name, metadata).await
+    }
+
+    async fn put_node(
+        &mut self,
+        plugin: &str,
+        name: &str,
+        dns_names: Vec<&str>,
+        exclusive: bool,
+        link_id: Option<&str>,
+    ) -> FCallResult<()> {
+        put_node(self, plugin, name, dns_names, exclusive, link_id).await
+    }
 
-        match (rtype, value) {
-            (Some(rtype), Some(value)) => Ok(cmd.arg(rtype).arg(value).exec_async(self).await?),
-            (None, None) => Ok(cmd.exec_async(self).await?),
-            _ => Err(FCallError::WrongArgs {
+    async fn put_node_plugin_data<'a>(
+        &mut self,
+        plugin: &str,
+        dns_names: Vec<&str>,
+        pdata_id: &str,
+        data: PluginData<'a>,
+    ) -> FCallResult<()> {
+        put_node_plugin_data(self, plugin, dns_names, pdata_id, data).await
+    }
+
+    async fn put_proc_node_plugin_data<'a>(
+        &mut self,
+        plugin: &str,
+        link_id: &str,
+        pdata_id: &str,
+        data: PluginData<'a>,
+    ) -> FCallResult<()> {
+        put_proc_node_plugin_data(self, plugin, link_id, pdata_id, data).await
+    }
+
+    async fn put_node_metadata(
+        &mut self,
+        plugin: &str,
+        dns_names: Vec<&str>,
+        metadata: &HashMap<&str, &str>,
+    ) -> FCallResult<()> {
+        put_node_metadata(self, plugin, dns_names, metadata).await
+    }
+
+    async fn put_proc_node_metadata(
+        &mut self,
+        plugin: &str,
+        link_id: &str,
+        metadata: &HashMap<&str, &str>,
+    ) -> FCallResult<()> {
+        put_proc_node_metadata(self, plugin, link_id, metadata).await
+    }
+
+    async fn put_report(
+        &mut self,
+        plugin: &str,
+        report_id: &str,
+        title: &str,
+        length: usize,
+    ) -> FCallResult<()> {
+        put_report(self, plugin, report_id, title, length).await
+    }
+
+    async fn put_report_data<'a>(
+        &mut self,
+        plugin: &str,
+        report_id: &str,
+        index: usize,
+        data: PluginData<'a>,
+    ) -> FCallResult<()> {
+        put_report_data(self, plugin, report_id, index, data).await
+    }
+
+    // Overridden so a batch isn't silently sent through WriteBatch::flush's
+    // MULTI/EXEC wrapper, which fails with CROSSSLOT as soon as the batch's
+    // keys span more than one slot - always a live risk on a cluster.
+    async fn put_batch(&mut self, batch: &mut WriteBatch) -> FCallResult<redis::Value> {
+        batch.flush_pipelined(self).await
+    }
+}
+
+/// Accumulates [`NetdoxWriter`] operations into a single [`redis::Pipeline`]
+/// so a batch of writes can be sent to redis in one round trip instead of
+/// one `FCALL` per method call.
+///
+/// [`WriteBatch::flush`] wraps the pipeline in `MULTI`/`EXEC`, which is only
+/// valid when every queued key lives on the same shard. Against a
+/// [`redis::cluster_async::ClusterConnection`] a batch spanning more than
+/// one slot fails with `CROSSSLOT` - use [`WriteBatch::flush_pipelined`]
+/// there instead, which sends the same commands without the transaction
+/// wrapper.
+#[derive(Default)]
+pub struct WriteBatch {
+    pipeline: redis::Pipeline,
+    /// The first error raised by a builder method, if any - kept here
+    /// rather than returned from the builder methods themselves so calls
+    /// can still be chained fluently; surfaced by [`WriteBatch::flush`]/
+    /// [`WriteBatch::flush_pipelined`] instead of sending an incomplete
+    /// batch.
+    error: Option<FCallError>,
+}
+
+impl WriteBatch {
+    /// Creates an empty batch.
+    pub fn new() -> Self {
+        WriteBatch::default()
+    }
+
+    /// Queues a DNS name creation. If `rtype`/`value` are invalid (exactly
+    /// one provided), records the error to be returned from the next
+    /// `flush`/`flush_pipelined` call instead of queuing a malformed
+    /// command.
+    pub fn put_dns(
+        &mut self,
+        plugin: &str,
+        name: &str,
+        rtype: Option<&str>,
+        value: Option<&str>,
+    ) -> &mut Self {
+        if !matches!((rtype, value), (Some(_), Some(_)) | (None, None)) {
+            self.error.get_or_insert(FCallError::WrongArgs {
                 function: CREATE_DNS_FN,
                 problem: "record type and value must both be provided or neiher provided.",
-            }),
+            });
+            return self;
         }
+
+        let cmd = self.pipeline.cmd("FCALL");
+        cmd.arg(CREATE_DNS_FN).arg(1).arg(name).arg(plugin);
+
+        if let (Some(rtype), Some(value)) = (rtype, value) {
+            cmd.arg(rtype).arg(value);
+        }
+
+        self
     }
 
-    async fn put_dns_plugin_data<'a>(
+    /// Queues plugin data attached to a DNS name.
+    pub fn put_dns_plugin_data<'a>(
         &mut self,
         plugin: &str,
         name: &str,
         pdata_id: &str,
         data: PluginData<'a>,
-    ) -> FCallResult<()> {
-        let mut cmd = redis::cmd("FCALL");
+    ) -> &mut Self {
+        let cmd = self.pipeline.cmd("FCALL");
         cmd.arg(CREATE_DNS_PDATA_FN)
             .arg(1)
             .arg(name)
             .arg(plugin)
             .arg(pdata_id);
 
-        data.add_as_args(&mut cmd);
+        data.add_as_args(cmd);
 
-        Ok(cmd.exec_async(self).await?)
+        self
     }
 
-    async fn put_dns_metadata(
+    /// Queues metadata attached to a DNS name.
+    pub fn put_dns_metadata(
         &mut self,
         plugin: &str,
         name: &str,
         metadata: &HashMap<&str, &str>,
-    ) -> FCallResult<()> {
-        let mut cmd = redis::cmd("FCALL");
+    ) -> &mut Self {
+        let cmd = self.pipeline.cmd("FCALL");
         cmd.arg(CREATE_DNS_METADATA_FN).arg(1).arg(name).arg(plugin);
 
         for (key, val) in metadata {
             cmd.arg(key).arg(val);
         }
 
-        Ok(cmd.exec_async(self).await?)
+        self
     }
 
-    // Nodes
-
-    async fn put_node(
+    /// Queues a node creation.
+    pub fn put_node(
         &mut self,
         plugin: &str,
         name: &str,
         dns_names: Vec<&str>,
         exclusive: bool,
         link_id: Option<&str>,
-    ) -> FCallResult<()> {
-        let mut cmd = redis::cmd("FCALL");
+    ) -> &mut Self {
+        let cmd = self.pipeline.cmd("FCALL");
         cmd.arg(CREATE_NODE_FN).arg(dns_names.len());
 
         for name in dns_names {
@@ -99,17 +549,18 @@ impl NetdoxWriter for redis::aio::MultiplexedConnection {
             cmd.arg(link_id);
         }
 
-        Ok(cmd.exec_async(self).await?)
+        self
     }
 
-    async fn put_node_plugin_data<'a>(
+    /// Queues plugin data attached to a node.
+    pub fn put_node_plugin_data<'a>(
         &mut self,
         plugin: &str,
         dns_names: Vec<&str>,
         pdata_id: &str,
         data: PluginData<'a>,
-    ) -> FCallResult<()> {
-        let mut cmd = redis::cmd("FCALL");
+    ) -> &mut Self {
+        let cmd = self.pipeline.cmd("FCALL");
         cmd.arg(CREATE_NODE_PDATA_FN).arg(dns_names.len());
 
         for name in dns_names {
@@ -118,37 +569,39 @@ impl NetdoxWriter for redis::aio::MultiplexedConnection {
 
         cmd.arg(plugin).arg(pdata_id);
 
-        data.add_as_args(&mut cmd);
+        data.add_as_args(cmd);
 
-        Ok(cmd.exec_async(self).await?)
+        self
     }
 
-    async fn put_proc_node_plugin_data<'a>(
+    /// Queues plugin data attached to a processed node by link ID.
+    pub fn put_proc_node_plugin_data<'a>(
         &mut self,
         plugin: &str,
         link_id: &str,
         pdata_id: &str,
         data: PluginData<'a>,
-    ) -> FCallResult<()> {
-        let mut cmd = redis::cmd("FCALL");
+    ) -> &mut Self {
+        let cmd = self.pipeline.cmd("FCALL");
         cmd.arg(CREATE_PROC_NODE_PDATA_FN)
             .arg(1)
             .arg(link_id)
             .arg(plugin)
             .arg(pdata_id);
 
-        data.add_as_args(&mut cmd);
+        data.add_as_args(cmd);
 
-        Ok(cmd.exec_async(self).await?)
+        self
     }
 
-    async fn put_node_metadata(
+    /// Queues metadata attached to a node.
+    pub fn put_node_metadata(
         &mut self,
         plugin: &str,
         dns_names: Vec<&str>,
         metadata: &HashMap<&str, &str>,
-    ) -> FCallResult<()> {
-        let mut cmd = redis::cmd("FCALL");
+    ) -> &mut Self {
+        let cmd = self.pipeline.cmd("FCALL");
         cmd.arg(CREATE_NODE_METADATA_FN).arg(dns_names.len());
         for name in dns_names {
             cmd.arg(name);
@@ -159,16 +612,17 @@ impl NetdoxWriter for redis::aio::MultiplexedConnection {
             cmd.arg(key).arg(val);
         }
 
-        Ok(cmd.exec_async(self).await?)
+        self
     }
 
-    async fn put_proc_node_metadata(
+    /// Queues metadata attached to a processed node by link ID.
+    pub fn put_proc_node_metadata(
         &mut self,
         plugin: &str,
         link_id: &str,
         metadata: &HashMap<&str, &str>,
-    ) -> FCallResult<()> {
-        let mut cmd = redis::cmd("FCALL");
+    ) -> &mut Self {
+        let cmd = self.pipeline.cmd("FCALL");
         cmd.arg(CREATE_PROC_NODE_METADATA_FN)
             .arg(1)
             .arg(link_id)
@@ -178,19 +632,18 @@ impl NetdoxWriter for redis::aio::MultiplexedConnection {
             cmd.arg(key).arg(val);
         }
 
-        Ok(cmd.exec_async(self).await?)
+        self
     }
 
-    // Reports
-
-    async fn put_report(
+    /// Queues a report creation.
+    pub fn put_report(
         &mut self,
         plugin: &str,
         report_id: &str,
         title: &str,
         length: usize,
-    ) -> FCallResult<()> {
-        let mut cmd = redis::cmd("FCALL");
+    ) -> &mut Self {
+        let cmd = self.pipeline.cmd("FCALL");
 
         cmd.arg(CREATE_REPORT_FN)
             .arg(1)
@@ -199,25 +652,57 @@ impl NetdoxWriter for redis::aio::MultiplexedConnection {
             .arg(title)
             .arg(length);
 
-        Ok(cmd.exec_async(self).await?)
+        self
     }
 
-    async fn put_report_data<'a>(
+    /// Queues data attached to a report.
+    pub fn put_report_data<'a>(
         &mut self,
         plugin: &str,
         report_id: &str,
         index: usize,
         data: PluginData<'a>,
-    ) -> FCallResult<()> {
-        let mut cmd = redis::cmd("FCALL");
+    ) -> &mut Self {
+        let cmd = self.pipeline.cmd("FCALL");
         cmd.arg(CREATE_REPORT_DATA_FN)
             .arg(1)
             .arg(report_id)
             .arg(plugin)
             .arg(index);
 
-        data.add_as_args(&mut cmd);
+        data.add_as_args(cmd);
+
+        self
+    }
 
-        Ok(cmd.exec_async(self).await?)
+    /// Sends all queued operations to redis as a single atomic (`MULTI`)
+    /// pipeline and returns the aggregate reply. Only valid when every
+    /// queued key lives on the same shard - see [`WriteBatch::flush_pipelined`]
+    /// for a cluster-safe alternative. Returns the first error raised by a
+    /// builder method, if any, instead of sending an incomplete batch.
+    pub async fn flush<C>(&mut self, conn: &mut C) -> FCallResult<redis::Value>
+    where
+        C: ConnectionLike + Send,
+    {
+        if let Some(err) = self.error.take() {
+            return Err(err);
+        }
+        Ok(self.pipeline.atomic().query_async(conn).await?)
+    }
+
+    /// Sends all queued operations to redis as a plain (non-atomic)
+    /// pipeline and returns the aggregate reply. Unlike [`WriteBatch::flush`]
+    /// this does not wrap the batch in `MULTI`/`EXEC`, so it's safe to use
+    /// against a [`redis::cluster_async::ClusterConnection`] even when the
+    /// queued keys span multiple slots. Returns the first error raised by a
+    /// builder method, if any, instead of sending an incomplete batch.
+    pub async fn flush_pipelined<C>(&mut self, conn: &mut C) -> FCallResult<redis::Value>
+    where
+        C: ConnectionLike + Send,
+    {
+        if let Some(err) = self.error.take() {
+            return Err(err);
+        }
+        Ok(self.pipeline.query_async(conn).await?)
     }
 }
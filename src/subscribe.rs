@@ -0,0 +1,179 @@
+//! Change-notification subscription over redis pub/sub, so plugins can react
+//! to incremental changes instead of only polling [`crate::model::NetdoxReader`].
+
+use std::time::Duration;
+
+use futures::{stream, Stream, StreamExt};
+
+use crate::error::FCallResult;
+
+/// A typed change event published by the netdox write functions, with a
+/// dynamic fallback for event kinds this crate doesn't know about yet.
+///
+/// Payloads are semicolon-delimited, mirroring the `key;subkey;...` layout
+/// used for redis keys elsewhere in this crate: `"<kind>;<field>;..."`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ChangeEvent {
+    /// A DNS name was created or given a new record.
+    DnsCreated { name: String, plugin: String },
+    /// A processed node was created or updated.
+    NodeUpdated { link_id: String, plugin: String },
+    /// Metadata was attached to a DNS name or node.
+    MetadataChanged { target: String, plugin: String },
+    /// An event kind this version of the crate doesn't recognise, along
+    /// with the raw payload so callers can still make use of it.
+    Unknown { kind: String, payload: String },
+}
+
+impl ChangeEvent {
+    /// Parses a raw pub/sub message payload into a [`ChangeEvent`], falling
+    /// back to [`ChangeEvent::Unknown`] for unrecognised or malformed kinds.
+    fn parse(payload: &str) -> Self {
+        let mut fields = payload.split(';');
+        match (fields.next(), fields.next(), fields.next()) {
+            (Some("dns_created"), Some(name), Some(plugin)) => ChangeEvent::DnsCreated {
+                name: name.to_string(),
+                plugin: plugin.to_string(),
+            },
+            (Some("node_updated"), Some(link_id), Some(plugin)) => ChangeEvent::NodeUpdated {
+                link_id: link_id.to_string(),
+                plugin: plugin.to_string(),
+            },
+            (Some("metadata_changed"), Some(target), Some(plugin)) => {
+                ChangeEvent::MetadataChanged {
+                    target: target.to_string(),
+                    plugin: plugin.to_string(),
+                }
+            }
+            (kind, ..) => ChangeEvent::Unknown {
+                kind: kind.unwrap_or_default().to_string(),
+                payload: payload.to_string(),
+            },
+        }
+    }
+}
+
+/// How long to wait before retrying a dropped or failed pub/sub connection.
+const RECONNECT_DELAY: Duration = Duration::from_secs(1);
+
+/// Defines the change-notification subscription API.
+pub trait NetdoxSubscriber {
+    /// Subscribes to `namespace`'s pattern (`{namespace};*`) and returns a
+    /// stream of parsed [`ChangeEvent`]s, reconnecting and resubscribing for
+    /// as long as the stream is polled if the underlying connection drops.
+    ///
+    /// The channel pattern uses `;` to separate the namespace from the rest
+    /// of the channel name, matching the `key;subkey;...` convention used
+    /// for redis keys and event payloads elsewhere in this crate - nothing
+    /// in this crate publishes to these channels itself, so the exact
+    /// channel names and payload grammar must match whatever the netdox
+    /// core publishes on write.
+    fn watch_changes(
+        &self,
+        namespace: &str,
+    ) -> impl std::future::Future<Output = FCallResult<impl Stream<Item = ChangeEvent> + Send>> + Send;
+}
+
+impl NetdoxSubscriber for redis::Client {
+    async fn watch_changes(
+        &self,
+        namespace: &str,
+    ) -> FCallResult<impl Stream<Item = ChangeEvent> + Send> {
+        let pattern = format!("{namespace};*");
+        let client = self.clone();
+
+        Ok(stream::unfold(
+            (client, pattern, None::<redis::aio::PubSub>),
+            |(client, pattern, mut pubsub)| async move {
+                loop {
+                    if pubsub.is_none() {
+                        pubsub = match connect_and_subscribe(&client, &pattern).await {
+                            Some(ps) => Some(ps),
+                            None => {
+                                tokio::time::sleep(RECONNECT_DELAY).await;
+                                continue;
+                            }
+                        };
+                    }
+
+                    let message = {
+                        let ps = pubsub.as_mut().expect("just ensured pubsub is connected");
+                        ps.on_message().next().await
+                    };
+
+                    match message {
+                        Some(msg) => {
+                            let payload: String = msg.get_payload().unwrap_or_default();
+                            return Some((ChangeEvent::parse(&payload), (client, pattern, pubsub)));
+                        }
+                        None => {
+                            // The connection was dropped; reconnect on the next iteration.
+                            pubsub = None;
+                        }
+                    }
+                }
+            },
+        ))
+    }
+}
+
+/// Opens a fresh pub/sub connection and subscribes to `pattern`, returning
+/// `None` (rather than an error) on failure so the caller can retry.
+async fn connect_and_subscribe(client: &redis::Client, pattern: &str) -> Option<redis::aio::PubSub> {
+    let mut pubsub = client.get_async_pubsub().await.ok()?;
+    pubsub.psubscribe(pattern).await.ok()?;
+    Some(pubsub)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parse_known_variants() {
+        let cases = [
+            (
+                "dns_created;example.com;test-plugin",
+                ChangeEvent::DnsCreated {
+                    name: "example.com".to_string(),
+                    plugin: "test-plugin".to_string(),
+                },
+            ),
+            (
+                "node_updated;link-id;test-plugin",
+                ChangeEvent::NodeUpdated {
+                    link_id: "link-id".to_string(),
+                    plugin: "test-plugin".to_string(),
+                },
+            ),
+            (
+                "metadata_changed;example.com;test-plugin",
+                ChangeEvent::MetadataChanged {
+                    target: "example.com".to_string(),
+                    plugin: "test-plugin".to_string(),
+                },
+            ),
+        ];
+
+        for (payload, expected) in cases {
+            assert_eq!(ChangeEvent::parse(payload), expected);
+        }
+    }
+
+    #[test]
+    fn parse_unknown_and_malformed_falls_back() {
+        let cases = [
+            "report_created;report-id;test-plugin",
+            "dns_created;example.com",
+            "dns_created",
+            "",
+        ];
+
+        for payload in cases {
+            match ChangeEvent::parse(payload) {
+                ChangeEvent::Unknown { payload: got, .. } => assert_eq!(got, payload),
+                other => panic!("expected Unknown for {payload:?}, got {other:?}"),
+            }
+        }
+    }
+}